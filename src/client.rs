@@ -7,12 +7,19 @@ https://postmarkapp.com/developer/api/email-api
 use crate::{
     base_url::BaseUrl,
     error::{self, Error},
+    inbound::Header,
+    transport::{SmtpTransport, Transport},
 };
+use async_trait::async_trait;
+use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use http::{HeaderMap, HeaderValue, StatusCode};
-use reqwest::Client;
+use rand::Rng;
+use reqwest::{Client, RequestBuilder, Response};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
+use std::time::Duration;
 
 type HtmlBody<'a> = &'a str;
 
@@ -46,12 +53,65 @@ pub struct Config {
 
     /// [Postmark's documentation](https://postmarkapp.com/developer/api/overview#authentication).
     pub server_token: String,
+
+    /// Retries transient `429`/5xx responses with exponential backoff. Off by default: a caller
+    /// has to opt in.
+    pub retry: Option<RetryPolicy>,
+
+    /// The backend [`PostmarkClient::send_email`] and [`PostmarkClient::send_email_batch`]
+    /// deliver through. Defaults to [`TransportKind::Http`].
+    pub transport: TransportKind,
+}
+
+/// Selects the backend [`PostmarkClient`] delivers mail through.
+///
+/// [`TemplatedEmail`]s always go through Postmark's HTTP API regardless of this setting, since
+/// server-side template rendering has no SMTP equivalent.
+#[derive(PartialEq, Clone, Debug, Default)]
+pub enum TransportKind {
+    /// Postmark's HTTP API. The default.
+    #[default]
+    Http,
+
+    /// Postmark's SMTP endpoint, for networks that block outbound HTTPS.
+    ///
+    /// [Postmark's documentation](https://postmarkapp.com/developer/user-guide/send-email-with-smtp).
+    Smtp,
+}
+
+/// [Postmark's documentation](https://postmarkapp.com/developer/api/overview#error-codes) on the
+/// rate limits this backs off from.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct RetryPolicy {
+    /// How many times to retry a retryable response before giving up.
+    pub max_retries: u32,
+
+    /// The delay before the first retry; each subsequent attempt doubles it, up to `max_delay`.
+    pub base_delay: Duration,
+
+    /// The ceiling the doubled delay is capped at, before jitter is added.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    fn delay_for(&self, attempt: u32) -> Duration {
+        let exponential = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+        let capped = exponential.min(self.max_delay);
+        let jitter = if self.base_delay.is_zero() {
+            Duration::ZERO
+        } else {
+            rand::thread_rng().gen_range(Duration::ZERO..self.base_delay)
+        };
+        capped.saturating_add(jitter)
+    }
 }
 
 #[derive(Debug)]
 pub struct PostmarkClient {
     base_url: BaseUrl,
     http: Client,
+    retry: Option<RetryPolicy>,
+    transport: Box<dyn Transport>,
 }
 
 impl PostmarkClient {
@@ -59,6 +119,8 @@ impl PostmarkClient {
         Config {
             base_url,
             server_token,
+            retry,
+            transport,
         }: Config,
     ) -> Result<Self, Error> {
         let base_url = base_url.unwrap_or_default();
@@ -78,36 +140,79 @@ impl PostmarkClient {
             Client::builder().default_headers(headers).build().unwrap()
         };
 
-        Ok(Self { base_url, http })
+        let transport: Box<dyn Transport> = match transport {
+            TransportKind::Http => Box::new(HttpTransport {
+                base_url: base_url.clone(),
+                http: http.clone(),
+                retry,
+            }),
+            TransportKind::Smtp => Box::new(SmtpTransport::new(&server_token)?),
+        };
+
+        Ok(Self {
+            base_url,
+            http,
+            retry,
+            transport,
+        })
+    }
+
+    /// Sends `request`, retrying transient `429`/5xx responses per [`Config::retry`] before
+    /// returning the final response to the caller.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        send_with_retry(request, self.retry.as_ref()).await
     }
 
     /// [Postmark's documentation](https://postmarkapp.com/developer/api/email-api#send-a-single-email).
-    pub async fn send_email(
+    pub async fn send_email(&self, email: OutboundEmail<'_>) -> Result<SendReceipt, Error> {
+        self.transport.send_email(email).await
+    }
+
+    /// Sends up to 500 messages in a single request.
+    ///
+    /// Unlike [`PostmarkClient::send_email`], a bad recipient in one message does not fail the
+    /// whole call: the result for each message is reported independently, in the same order as
+    /// `emails`.
+    ///
+    /// [Postmark's documentation](https://postmarkapp.com/developer/api/email-api#send-batch-emails).
+    pub async fn send_email_batch(
+        &self,
+        emails: &[OutboundEmail<'_>],
+    ) -> Result<Vec<Result<SendReceipt, ErrorReceipt>>, Error> {
+        self.transport.send_email_batch(emails).await
+    }
+
+    /// Sends an email rendered server-side from a template and a model of substitution data.
+    ///
+    /// [Postmark's documentation](https://postmarkapp.com/developer/api/templates-api#email-with-template).
+    pub async fn send_email_with_template<T>(
         &self,
-        sender: &str,
-        message_stream: Option<&str>,
-        email: OutboundEmail<'_>,
-    ) -> Result<SendReceipt, Error> {
+        email: TemplatedEmail<'_>,
+        template_model: &T,
+    ) -> Result<SendReceipt, Error>
+    where
+        T: Serialize,
+    {
         let url = {
             let mut url = self.base_url.clone().into_inner();
-            url.path_segments_mut().unwrap().push("email");
+            url.path_segments_mut().unwrap().push("email").push("withTemplate");
             url
         };
 
         let recipients = email.recipients.join(",");
-        let (html_body, text_body) = email.body.into_tuple();
-        let payload = SendEmailPayload {
-            from: sender,
+        let (template_id, template_alias) = email.template.into_tuple();
+        let payload = SendEmailWithTemplatePayload {
+            from: email.sender,
             to: &recipients,
-            subject: &email.subject,
-            html_body,
-            text_body,
-            message_stream,
+            template_id,
+            template_alias,
+            template_model,
+            message_stream: email.message_stream,
         };
 
         let request = self.http.post(url).json(&payload);
 
-        let response = request.send().await?;
+        let response = self.send(request).await?;
 
         let status_code = response.status();
         match status_code {
@@ -121,11 +226,125 @@ impl PostmarkClient {
     }
 }
 
-#[derive(PartialEq, Copy, Clone, Debug)]
+/// Sends `request`, retrying transient `429`/5xx responses per `retry` before returning the final
+/// response to the caller. Shared by [`PostmarkClient::send`] and [`HttpTransport`].
+async fn send_with_retry(request: RequestBuilder, retry: Option<&RetryPolicy>) -> Result<Response, Error> {
+    let mut attempt = 0;
+
+    loop {
+        let response = request
+            .try_clone()
+            .expect("request body is buffered JSON, so it must be clonable")
+            .send()
+            .await?;
+
+        let Some(retry) = retry else {
+            return Ok(response);
+        };
+
+        let status = response.status();
+        let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+        if !retryable || attempt >= retry.max_retries {
+            return Ok(response);
+        }
+
+        let delay = retry_after(&response).unwrap_or_else(|| retry.delay_for(attempt));
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Reads a `Retry-After` header, in either its delta-seconds or HTTP-date form, as the
+/// [`Duration`] to wait before the next attempt.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(http::header::RETRY_AFTER)?.to_str().ok()?;
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    (at - Utc::now()).to_std().ok()
+}
+
+#[derive(PartialEq, Clone, Debug)]
 pub struct OutboundEmail<'a> {
+    /// The sender's email address, must be on a verified domain or Sender Signature.
+    pub sender: &'a str,
+
+    pub message_stream: Option<&'a str>,
+
     pub recipients: &'a [&'a str],
+    pub cc: Option<&'a [&'a str]>,
+    pub bcc: Option<&'a [&'a str]>,
+    pub reply_to: Option<&'a str>,
+
     pub subject: &'a str,
     pub body: EmailBody<'a>,
+
+    pub tag: Option<&'a str>,
+    pub headers: Option<Vec<Header>>,
+    pub metadata: Option<HashMap<String, String>>,
+    pub attachments: Option<Vec<Attachment<'a>>>,
+}
+
+impl<'a> OutboundEmail<'a> {
+    fn to_payload<'p>(&'p self, recipients: &'p JoinedRecipients) -> SendEmailPayload<'p>
+    where
+        'a: 'p,
+    {
+        let (html_body, text_body) = self.body.into_tuple();
+        SendEmailPayload {
+            from: self.sender,
+            to: &recipients.to,
+            cc: recipients.cc.as_deref(),
+            bcc: recipients.bcc.as_deref(),
+            reply_to: self.reply_to,
+            subject: self.subject,
+            html_body,
+            text_body,
+            message_stream: self.message_stream,
+            tag: self.tag,
+            headers: self.headers.as_deref(),
+            metadata: self.metadata.as_ref(),
+            attachments: self.attachments.as_deref(),
+        }
+    }
+}
+
+/// The recipient address lists of an [`OutboundEmail`], pre-joined into the comma-separated
+/// strings Postmark expects on the wire.
+struct JoinedRecipients {
+    to: String,
+    cc: Option<String>,
+    bcc: Option<String>,
+}
+
+impl From<&OutboundEmail<'_>> for JoinedRecipients {
+    fn from(email: &OutboundEmail<'_>) -> Self {
+        Self {
+            to: email.recipients.join(","),
+            cc: email.cc.map(|cc| cc.join(",")),
+            bcc: email.bcc.map(|bcc| bcc.join(",")),
+        }
+    }
+}
+
+/// A file attached to an [`OutboundEmail`].
+#[derive(Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Attachment<'a> {
+    pub name: &'a str,
+
+    #[serde(with = "crate::serde_helpers::base64_serde")]
+    pub content: Bytes,
+
+    pub content_type: &'a str,
+
+    /// Set this to reference the attachment from a `cid:` URL in the HTML body, embedding it
+    /// inline instead of showing it as a downloadable file.
+    #[serde(rename = "ContentID")]
+    pub content_id: Option<&'a str>,
 }
 
 #[derive(Serialize, PartialEq, Copy, Clone, Debug)]
@@ -133,10 +352,56 @@ pub struct OutboundEmail<'a> {
 struct SendEmailPayload<'a> {
     from: &'a str,
     to: &'a str,
+    cc: Option<&'a str>,
+    bcc: Option<&'a str>,
+    reply_to: Option<&'a str>,
     subject: &'a str,
     html_body: Option<&'a str>,
     text_body: Option<&'a str>,
     message_stream: Option<&'a str>,
+    tag: Option<&'a str>,
+    headers: Option<&'a [Header]>,
+    metadata: Option<&'a HashMap<String, String>>,
+    attachments: Option<&'a [Attachment<'a>]>,
+}
+
+/// Identifies which template a [`TemplatedEmail`] is rendered from.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum TemplatedBody<'a> {
+    Id(i64),
+    Alias(&'a str),
+}
+
+impl<'a> TemplatedBody<'a> {
+    /// Converts TemplatedBody sumtype into a pair of template ID and template alias.
+    fn into_tuple(self) -> (Option<i64>, Option<&'a str>) {
+        match self {
+            TemplatedBody::Id(id) => (id.into(), None),
+            TemplatedBody::Alias(alias) => (None, alias.into()),
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct TemplatedEmail<'a> {
+    /// The sender's email address, must be on a verified domain or Sender Signature.
+    pub sender: &'a str,
+
+    pub message_stream: Option<&'a str>,
+
+    pub recipients: &'a [&'a str],
+    pub template: TemplatedBody<'a>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct SendEmailWithTemplatePayload<'a, T> {
+    from: &'a str,
+    to: &'a str,
+    template_id: Option<i64>,
+    template_alias: Option<&'a str>,
+    template_model: &'a T,
+    message_stream: Option<&'a str>,
 }
 
 #[derive(Deserialize, PartialEq, Clone, Debug)]
@@ -162,11 +427,135 @@ impl fmt::Display for ErrorReceipt {
     }
 }
 
+/// A single element of a batch send response.
+///
+/// Postmark reports success and failure through the same shape, distinguished by `ErrorCode`
+/// being `0`, so this is deserialized once and then split into a [`SendReceipt`] or
+/// [`ErrorReceipt`] via [`BatchReceipt::into_result`].
+#[derive(Deserialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct BatchReceipt {
+    error_code: u16,
+    message: String,
+    to: Option<String>,
+    submitted_at: Option<DateTime<Utc>>,
+
+    #[serde(rename = "MessageID")]
+    message_id: Option<String>,
+}
+
+impl BatchReceipt {
+    fn into_result(self) -> Result<SendReceipt, ErrorReceipt> {
+        if self.error_code != 0 {
+            return Err(ErrorReceipt {
+                error_code: self.error_code,
+                message: self.message,
+            });
+        }
+
+        match (self.to, self.submitted_at, self.message_id) {
+            (Some(to), Some(submitted_at), Some(message_id)) => Ok(SendReceipt {
+                to,
+                submitted_at,
+                message_id,
+            }),
+            // A malformed item shouldn't panic the whole batch, so report it alongside real
+            // failures instead.
+            _ => Err(ErrorReceipt {
+                error_code: 1,
+                message: "batch item reported success but was missing `To`, `SubmittedAt` or `MessageID`".to_owned(),
+            }),
+        }
+    }
+}
+
+/// The default [`Transport`], talking to Postmark's HTTP API.
+#[derive(Debug)]
+struct HttpTransport {
+    base_url: BaseUrl,
+    http: Client,
+    retry: Option<RetryPolicy>,
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn send_email(&self, email: OutboundEmail<'_>) -> Result<SendReceipt, Error> {
+        let url = {
+            let mut url = self.base_url.clone().into_inner();
+            url.path_segments_mut().unwrap().push("email");
+            url
+        };
+
+        let recipients = JoinedRecipients::from(&email);
+        let payload = email.to_payload(&recipients);
+
+        let request = self.http.post(url).json(&payload);
+
+        let response = send_with_retry(request, self.retry.as_ref()).await?;
+
+        let status_code = response.status();
+        match status_code {
+            StatusCode::OK => Ok(response.json().await?),
+            StatusCode::UNPROCESSABLE_ENTITY => {
+                let error = response.json().await?;
+                Err(error::PostmarkError::UnprocessableEntity(error).into())
+            }
+            _ => Err(error::PostmarkError::Other(status_code).into()),
+        }
+    }
+
+    async fn send_email_batch(
+        &self,
+        emails: &[OutboundEmail<'_>],
+    ) -> Result<Vec<Result<SendReceipt, ErrorReceipt>>, Error> {
+        let url = {
+            let mut url = self.base_url.clone().into_inner();
+            url.path_segments_mut().unwrap().push("email").push("batch");
+            url
+        };
+
+        let recipients: Vec<JoinedRecipients> = emails.iter().map(JoinedRecipients::from).collect();
+        let payloads: Vec<SendEmailPayload> = emails
+            .iter()
+            .zip(&recipients)
+            .map(|(email, recipients)| email.to_payload(recipients))
+            .collect();
+
+        let request = self.http.post(url).json(&payloads);
+
+        let response = send_with_retry(request, self.retry.as_ref()).await?;
+
+        let status_code = response.status();
+        match status_code {
+            StatusCode::OK => {
+                let receipts: Vec<BatchReceipt> = response.json().await?;
+                Ok(receipts.into_iter().map(BatchReceipt::into_result).collect())
+            }
+            _ => Err(error::PostmarkError::Other(status_code).into()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::{FixedOffset, TimeZone};
 
+    #[test]
+    fn retry_policy_delay_is_capped_at_max_delay() {
+        let retry = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+
+        // at attempt 5, 100ms * 2^5 = 3200ms would far exceed the 300ms cap.
+        let delay = retry.delay_for(5);
+
+        assert!(delay >= Duration::from_millis(300));
+        assert!(delay <= Duration::from_millis(300) + retry.base_delay);
+    }
+
     #[test]
     fn deserialize_error_receipt() {
         let json = r#"
@@ -213,4 +602,72 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn batch_receipt_into_result_ok() {
+        let json = r#"
+            {
+                "To": "receiver@example.com",
+                "SubmittedAt": "2014-02-17T07:25:01.4178645-05:00",
+                "MessageID": "0a129aee-e1cd-480d-b08d-4f48548ff48d",
+                "ErrorCode": 0,
+                "Message": "OK"
+            }
+        "#;
+
+        let receipt: BatchReceipt = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            receipt.into_result(),
+            Ok(SendReceipt {
+                to: "receiver@example.com".to_owned(),
+                submitted_at: FixedOffset::west(5 * 60 * 60)
+                    .ymd(2014, 2, 17)
+                    .and_hms_nano(7, 25, 1, 417864500)
+                    .with_timezone(&Utc),
+                message_id: "0a129aee-e1cd-480d-b08d-4f48548ff48d".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn batch_receipt_into_result_err() {
+        let json = r#"
+            {
+                "ErrorCode": 405,
+                "Message": "Not allowed to send"
+            }
+        "#;
+
+        let receipt: BatchReceipt = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            receipt.into_result(),
+            Err(ErrorReceipt {
+                error_code: 405,
+                message: "Not allowed to send".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn batch_receipt_into_result_malformed_success() {
+        let json = r#"
+            {
+                "ErrorCode": 0,
+                "Message": "OK"
+            }
+        "#;
+
+        let receipt: BatchReceipt = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            receipt.into_result(),
+            Err(ErrorReceipt {
+                error_code: 1,
+                message: "batch item reported success but was missing `To`, `SubmittedAt` or `MessageID`"
+                    .to_owned(),
+            })
+        );
+    }
 }
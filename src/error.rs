@@ -13,6 +13,44 @@ pub enum Error {
 
     #[error(transparent)]
     Postmark(#[from] PostmarkError),
+
+    /// Returned by the `from_json` constructors, e.g. [`crate::InboundEmail::from_json`], with
+    /// `path` pointing at the field that failed to deserialize.
+    #[error("failed to deserialize at `{path}`")]
+    Deserialize {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error(transparent)]
+    InvalidAddress(#[from] lettre::address::AddressError),
+
+    #[error(transparent)]
+    Message(#[from] lettre::error::Error),
+
+    #[error(transparent)]
+    SmtpClient(#[from] lettre::transport::smtp::Error),
+
+    /// Returned by [`crate::SmtpTransport`] when a custom header name or a metadata key isn't a
+    /// valid SMTP header name.
+    #[error("invalid header name `{0}`")]
+    InvalidHeaderName(String),
+
+    /// Returned by [`crate::SmtpTransport`] when an attachment's `content_type` isn't a valid
+    /// MIME type.
+    #[error("invalid attachment content type `{0}`")]
+    InvalidContentType(String),
+}
+
+impl Error {
+    pub(crate) fn from_path_to_error(err: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let path = err.path().to_string();
+        Error::Deserialize {
+            path,
+            source: err.into_inner(),
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -4,6 +4,7 @@ Types for Postmark's inbound webhook.
 https://postmarkapp.com/developer/webhooks/inbound-webhook
 */
 
+use crate::Error;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -30,7 +31,7 @@ pub struct InboundEmail {
     pub reply_to: String,
     pub mailbox_hash: String,
 
-    #[serde(with = "rfc2822_serde")]
+    #[serde(with = "crate::serde_helpers::rfc2822_serde")]
     #[cfg_attr(test, arbitrary(generator = "gen_date_time_utc"))]
     pub date: DateTime<Utc>,
 
@@ -42,6 +43,15 @@ pub struct InboundEmail {
     pub attachments: Vec<Attachment>,
 }
 
+impl InboundEmail {
+    /// Deserializes an inbound webhook payload, reporting the exact field path on failure (e.g.
+    /// `Headers[2].Value`) rather than serde_json's plain "invalid type" message.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        serde_path_to_error::deserialize(&mut deserializer).map_err(Error::from_path_to_error)
+    }
+}
+
 #[cfg(test)]
 fn gen_date_time_utc(g: &mut quickcheck::Gen) -> DateTime<Utc> {
     use chrono::TimeZone;
@@ -49,47 +59,6 @@ fn gen_date_time_utc(g: &mut quickcheck::Gen) -> DateTime<Utc> {
     Utc.timestamp(u32::arbitrary(g) as _, 0)
 }
 
-mod rfc2822_serde {
-    use chrono::{DateTime, Utc};
-    use serde::de::{self, Visitor};
-    use serde::{Deserializer, Serializer};
-    use std::fmt;
-
-    const FORMAT: &str = "%a, %-d %b %Y %H:%M:%S %:z";
-
-    pub fn deserialize<'de, D>(d: D) -> Result<DateTime<Utc>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        struct V;
-        impl<'de> Visitor<'de> for V {
-            type Value = DateTime<Utc>;
-
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                write!(formatter, "RFC2822 string")
-            }
-
-            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                DateTime::parse_from_str(v, FORMAT)
-                    .map(|x| x.with_timezone(&Utc))
-                    .map_err(|x| de::Error::custom(x))
-            }
-        }
-        d.deserialize_str(V)
-    }
-
-    pub fn serialize<S>(v: &DateTime<Utc>, s: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let str = v.format(FORMAT).to_string();
-        s.serialize_str(&str)
-    }
-}
-
 #[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
 #[cfg_attr(test, derive(Arbitrary))]
 #[serde(rename_all = "PascalCase")]
@@ -113,7 +82,7 @@ pub struct Header {
 pub struct Attachment {
     pub name: String,
 
-    #[serde(with = "base64_serde")]
+    #[serde(with = "crate::serde_helpers::base64_serde")]
     #[cfg_attr(test, arbitrary(generator = "gen_bytes"))]
     pub content: Bytes,
 
@@ -127,44 +96,6 @@ fn gen_bytes(g: &mut quickcheck::Gen) -> Bytes {
     Bytes::from(Vec::<u8>::arbitrary(g))
 }
 
-mod base64_serde {
-    use bytes::Bytes;
-    use serde::de::{self, Visitor};
-    use serde::{Deserializer, Serializer};
-    use std::fmt;
-
-    pub fn deserialize<'de, D>(d: D) -> Result<Bytes, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        struct V;
-        impl<'de> Visitor<'de> for V {
-            type Value = Bytes;
-
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                write!(formatter, "Base64 string")
-            }
-
-            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                base64::decode(v)
-                    .map(Bytes::from)
-                    .map_err(|x| de::Error::custom(x))
-            }
-        }
-        d.deserialize_str(V)
-    }
-
-    pub fn serialize<S>(v: &Bytes, s: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        s.serialize_str(&base64::encode(&*v))
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,4 +105,34 @@ mod tests {
     fn test_serde_is_identity(v: InboundEmail) -> bool {
         serde_json::from_str::<InboundEmail>(&serde_json::to_string(&v).unwrap()).unwrap() == v
     }
+
+    #[test]
+    fn from_json_reports_path_of_malformed_field() {
+        let json = r#"
+            {
+                "FromName": "name",
+                "MessageStream": "stream",
+                "FromFull": {"Email": "a@b.com", "Name": "", "MailboxHash": ""},
+                "ToFull": [],
+                "CcFull": [],
+                "BccFull": [],
+                "OriginalRecipient": "",
+                "Subject": "",
+                "MessageID": "",
+                "ReplyTo": "",
+                "MailboxHash": "",
+                "Date": "Thu, 5 Apr 2012 16:59:01 -0400",
+                "TextBody": "",
+                "HtmlBody": "",
+                "StrippedTextReply": "",
+                "Tag": "",
+                "Headers": [{"Name": "X-Test", "Value": 42}],
+                "Attachments": []
+            }
+        "#;
+
+        let error = InboundEmail::from_json(json).unwrap_err();
+
+        assert_eq!(error.to_string(), "failed to deserialize at `Headers[0].Value`");
+    }
 }
@@ -4,7 +4,18 @@ mod base_url;
 mod client;
 mod error;
 mod inbound;
+mod serde_helpers;
+mod transport;
+mod webhooks;
 
-pub use client::{Config, EmailBody, ErrorReceipt, OutboundEmail, PostmarkClient, SendReceipt};
+pub use client::{
+    Attachment, Config, EmailBody, ErrorReceipt, OutboundEmail, PostmarkClient, RetryPolicy,
+    SendReceipt, TemplatedBody, TemplatedEmail, TransportKind,
+};
 pub use error::{Error, PostmarkError};
-pub use inbound::InboundEmail;
+pub use inbound::{Header, InboundEmail};
+pub use transport::{SmtpTransport, Transport};
+pub use webhooks::{
+    BounceEvent, Client, ClickEvent, DeliveryEvent, Geo, OpenEvent, OperatingSystem,
+    SpamComplaintEvent, SubscriptionChangeEvent, WebhookEvent,
+};
@@ -0,0 +1,82 @@
+/*!
+Shared `serde::with` helpers for Postmark's wire formats.
+*/
+
+pub(crate) mod rfc2822_serde {
+    use chrono::{DateTime, Utc};
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    const FORMAT: &str = "%a, %-d %b %Y %H:%M:%S %:z";
+
+    pub fn deserialize<'de, D>(d: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+        impl<'de> Visitor<'de> for V {
+            type Value = DateTime<Utc>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "RFC2822 string")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                DateTime::parse_from_str(v, FORMAT)
+                    .map(|x| x.with_timezone(&Utc))
+                    .map_err(|x| de::Error::custom(x))
+            }
+        }
+        d.deserialize_str(V)
+    }
+
+    pub fn serialize<S>(v: &DateTime<Utc>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let str = v.format(FORMAT).to_string();
+        s.serialize_str(&str)
+    }
+}
+
+pub(crate) mod base64_serde {
+    use bytes::Bytes;
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Bytes, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct V;
+        impl<'de> Visitor<'de> for V {
+            type Value = Bytes;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "Base64 string")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                base64::decode(v)
+                    .map(Bytes::from)
+                    .map_err(|x| de::Error::custom(x))
+            }
+        }
+        d.deserialize_str(V)
+    }
+
+    pub fn serialize<S>(v: &Bytes, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.serialize_str(&base64::encode(&*v))
+    }
+}
@@ -0,0 +1,203 @@
+/*!
+Pluggable delivery backends for [`OutboundEmail`].
+
+[`PostmarkClient`](crate::PostmarkClient) ships with Postmark's HTTP API as the default backend,
+but some networks block outbound HTTPS while still allowing SMTP submission, so a second,
+`lettre`-backed backend is also provided.
+*/
+
+use crate::{
+    client::{Attachment, EmailBody, ErrorReceipt, OutboundEmail, SendReceipt},
+    error::Error,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::{
+    message::{
+        header::{ContentType, Header as LettreHeader, HeaderName, HeaderValue, MessageId},
+        Attachment as LettreAttachment, Mailbox, Message, MultiPart, SinglePart,
+    },
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
+};
+
+/// A backend capable of delivering an [`OutboundEmail`].
+///
+/// Postmark's HTTP API is the default, selected via [`TransportKind::Http`](crate::TransportKind);
+/// [`SmtpTransport`] is the fallback for networks where that's blocked.
+#[async_trait]
+pub trait Transport: std::fmt::Debug + Send + Sync {
+    async fn send_email(&self, email: OutboundEmail<'_>) -> Result<SendReceipt, Error>;
+
+    async fn send_email_batch(
+        &self,
+        emails: &[OutboundEmail<'_>],
+    ) -> Result<Vec<Result<SendReceipt, ErrorReceipt>>, Error>;
+}
+
+/// Relays mail through Postmark's SMTP endpoint using the server token as both SMTP username and
+/// password.
+///
+/// The envelope (sender, recipients, reply-to), body and attachments are carried over in full.
+/// `tag` and `metadata` have no SMTP equivalent in the envelope, so they're sent the same way
+/// Postmark's own SMTP integrations send them: as `X-PM-Tag` and `X-PM-Metadata-*` headers,
+/// alongside any custom `headers` the caller set.
+///
+/// [Postmark's documentation](https://postmarkapp.com/developer/user-guide/send-email-with-smtp).
+#[derive(Debug)]
+pub struct SmtpTransport {
+    smtp: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    pub fn new(server_token: &str) -> Result<Self, Error> {
+        let smtp = AsyncSmtpTransport::<Tokio1Executor>::relay("smtp.postmarkapp.com")?
+            .credentials(Credentials::new(server_token.to_owned(), server_token.to_owned()))
+            .build();
+
+        Ok(Self { smtp })
+    }
+
+    fn to_message(email: &OutboundEmail<'_>) -> Result<Message, Error> {
+        let mut builder = Message::builder()
+            .from(email.sender.parse::<Mailbox>()?)
+            .subject(email.subject);
+
+        for recipient in email.recipients {
+            builder = builder.to(recipient.parse::<Mailbox>()?);
+        }
+        for recipient in email.cc.unwrap_or_default() {
+            builder = builder.cc(recipient.parse::<Mailbox>()?);
+        }
+        for recipient in email.bcc.unwrap_or_default() {
+            builder = builder.bcc(recipient.parse::<Mailbox>()?);
+        }
+        if let Some(reply_to) = email.reply_to {
+            builder = builder.reply_to(reply_to.parse::<Mailbox>()?);
+        }
+        if let Some(tag) = email.tag {
+            builder = builder.header(RawHeader::named("X-PM-Tag", tag.to_owned())?);
+        }
+        for header in email.headers.iter().flatten() {
+            builder = builder.header(RawHeader::named(&header.name, header.value.clone())?);
+        }
+        for (key, value) in email.metadata.iter().flatten() {
+            builder = builder.header(RawHeader::named(&format!("X-PM-Metadata-{key}"), value.clone())?);
+        }
+
+        let attachments = email.attachments.as_deref().unwrap_or_default();
+
+        let message = if attachments.is_empty() {
+            match email.body {
+                EmailBody::Html(html) => builder
+                    .header(ContentType::TEXT_HTML)
+                    .body(html.to_owned())?,
+                EmailBody::Text(text) => builder
+                    .header(ContentType::TEXT_PLAIN)
+                    .body(text.to_owned())?,
+                EmailBody::Both { html, text } => builder.multipart(
+                    MultiPart::alternative()
+                        .singlepart(SinglePart::plain(text.to_owned()))
+                        .singlepart(SinglePart::html(html.to_owned())),
+                )?,
+            }
+        } else {
+            let alternative = match email.body {
+                EmailBody::Html(html) => MultiPart::alternative().singlepart(SinglePart::html(html.to_owned())),
+                EmailBody::Text(text) => MultiPart::alternative().singlepart(SinglePart::plain(text.to_owned())),
+                EmailBody::Both { html, text } => MultiPart::alternative()
+                    .singlepart(SinglePart::plain(text.to_owned()))
+                    .singlepart(SinglePart::html(html.to_owned())),
+            };
+
+            let mut mixed = MultiPart::mixed().multipart(alternative);
+            for attachment in attachments {
+                mixed = mixed.singlepart(Self::to_attachment_part(attachment)?);
+            }
+
+            builder.multipart(mixed)?
+        };
+
+        Ok(message)
+    }
+
+    fn to_attachment_part(attachment: &Attachment<'_>) -> Result<SinglePart, Error> {
+        let content_type = ContentType::parse(attachment.content_type)
+            .map_err(|_| Error::InvalidContentType(attachment.content_type.to_owned()))?;
+
+        let builder = match attachment.content_id {
+            Some(content_id) => LettreAttachment::new_inline(content_id.to_owned()),
+            None => LettreAttachment::new(attachment.name.to_owned()),
+        };
+
+        Ok(builder.body(attachment.content.to_vec(), content_type))
+    }
+}
+
+/// A header whose name is only known at runtime: Postmark's `X-PM-Tag`/`X-PM-Metadata-*` headers
+/// and the caller's own custom `headers` all fall outside `lettre`'s statically-named `Header`
+/// impls, so this carries the name alongside the value instead.
+#[derive(Clone)]
+struct RawHeader {
+    name: HeaderName,
+    value: String,
+}
+
+impl RawHeader {
+    fn named(name: &str, value: String) -> Result<Self, Error> {
+        let name = HeaderName::new_from_ascii(name.to_owned())
+            .map_err(|_| Error::InvalidHeaderName(name.to_owned()))?;
+        Ok(Self { name, value })
+    }
+}
+
+impl LettreHeader for RawHeader {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii("X-Postmarkee-Raw".to_owned())
+            .expect("static header name is valid ASCII")
+    }
+
+    fn parse(_s: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        unreachable!("RawHeader is only ever constructed directly, never parsed from a response")
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(self.name.clone(), self.value.clone())
+    }
+}
+
+#[async_trait]
+impl Transport for SmtpTransport {
+    async fn send_email(&self, email: OutboundEmail<'_>) -> Result<SendReceipt, Error> {
+        let to = email.recipients.join(",");
+        let message = Self::to_message(&email)?;
+        let message_id = message
+            .headers()
+            .get::<MessageId>()
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+
+        self.smtp.send(message).await?;
+
+        Ok(SendReceipt {
+            to,
+            submitted_at: Utc::now(),
+            message_id,
+        })
+    }
+
+    async fn send_email_batch(
+        &self,
+        emails: &[OutboundEmail<'_>],
+    ) -> Result<Vec<Result<SendReceipt, ErrorReceipt>>, Error> {
+        let mut results = Vec::with_capacity(emails.len());
+        for email in emails {
+            let result = self.send_email(email.clone()).await.map_err(|error| ErrorReceipt {
+                error_code: 1,
+                message: error.to_string(),
+            });
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,526 @@
+/*!
+Types for Postmark's outbound webhooks: bounce, delivery, open, click, spam complaint and
+subscription change notifications.
+
+https://postmarkapp.com/developer/webhooks/webhooks-overview
+*/
+
+use crate::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Dispatches a raw webhook payload to the event it carries, keyed on `RecordType`.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(tag = "RecordType")]
+pub enum WebhookEvent {
+    Bounce(BounceEvent),
+    Delivery(DeliveryEvent),
+    Open(OpenEvent),
+    Click(ClickEvent),
+    SpamComplaint(SpamComplaintEvent),
+    SubscriptionChange(SubscriptionChangeEvent),
+}
+
+impl WebhookEvent {
+    /// Deserializes a webhook payload, wrapping a failure in [`Error::Deserialize`].
+    ///
+    /// The event body is buffered while `RecordType` is located, so the path is not tracked into
+    /// the event's fields: a malformed field inside the event is reported at the root path `.`.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        serde_path_to_error::deserialize(&mut deserializer).map_err(Error::from_path_to_error)
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct BounceEvent {
+    #[serde(rename = "ID")]
+    pub id: i64,
+
+    pub r#type: String,
+    pub type_code: i32,
+    pub name: String,
+    pub tag: String,
+
+    #[serde(rename = "MessageID")]
+    pub message_id: String,
+
+    #[serde(rename = "ServerID")]
+    pub server_id: i64,
+
+    pub description: String,
+    pub details: String,
+    pub email: String,
+    pub from: String,
+    pub bounced_at: DateTime<Utc>,
+    pub inactive: bool,
+    pub dump_available: bool,
+    pub can_activate: bool,
+    pub subject: String,
+    pub content: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct DeliveryEvent {
+    #[serde(rename = "MessageID")]
+    pub message_id: String,
+
+    #[serde(rename = "ServerID")]
+    pub server_id: i64,
+
+    pub recipient: String,
+    pub delivered_at: DateTime<Utc>,
+    pub details: String,
+    pub tag: String,
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct OpenEvent {
+    pub first_open: bool,
+    pub client: Client,
+
+    #[serde(rename = "OS")]
+    pub os: OperatingSystem,
+
+    pub platform: String,
+    pub user_agent: String,
+    pub read_seconds: i32,
+    pub geo: Geo,
+
+    #[serde(rename = "MessageID")]
+    pub message_id: String,
+
+    pub received_at: DateTime<Utc>,
+    pub tag: String,
+    pub recipient: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct ClickEvent {
+    pub original_link: String,
+    pub click_location: String,
+    pub client: Client,
+
+    #[serde(rename = "OS")]
+    pub os: OperatingSystem,
+
+    pub platform: String,
+    pub user_agent: String,
+    pub geo: Geo,
+
+    #[serde(rename = "MessageID")]
+    pub message_id: String,
+
+    pub received_at: DateTime<Utc>,
+    pub tag: String,
+    pub recipient: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct SpamComplaintEvent {
+    #[serde(rename = "ID")]
+    pub id: i64,
+
+    pub r#type: String,
+    pub type_code: i32,
+    pub name: String,
+    pub tag: String,
+
+    #[serde(rename = "MessageID")]
+    pub message_id: String,
+
+    #[serde(rename = "ServerID")]
+    pub server_id: i64,
+
+    pub description: String,
+    pub details: String,
+    pub email: String,
+    pub from: String,
+    pub bounced_at: DateTime<Utc>,
+    pub inactive: bool,
+    pub dump_available: bool,
+    pub can_activate: bool,
+    pub subject: String,
+    pub content: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct SubscriptionChangeEvent {
+    #[serde(rename = "MessageID")]
+    pub message_id: String,
+
+    #[serde(rename = "ServerID")]
+    pub server_id: i64,
+
+    pub changed_at: DateTime<Utc>,
+    pub origin: String,
+    pub recipient: String,
+    pub suppress_sending: bool,
+    pub suppression_reason: String,
+    pub tag: String,
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Client {
+    pub name: String,
+    pub company: String,
+    pub family: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct OperatingSystem {
+    pub name: String,
+    pub company: String,
+    pub family: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Geo {
+    #[serde(rename = "CountryISOCode")]
+    pub country_iso_code: String,
+
+    pub country: String,
+
+    #[serde(rename = "RegionISOCode")]
+    pub region_iso_code: String,
+
+    pub region: String,
+    pub city: String,
+    pub zip: String,
+    pub coords: String,
+
+    #[serde(rename = "IP")]
+    pub ip: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn dispatch_delivery_event() {
+        let json = r#"
+            {
+                "RecordType": "Delivery",
+                "ServerID": 23,
+                "MessageID": "0a129aee-e1cd-480d-b08d-4f48548ff48d",
+                "Recipient": "receiver@example.com",
+                "DeliveredAt": "2019-11-14T08:45:16.7888103Z",
+                "Details": "Test delivery webhook",
+                "Tag": "welcome-email",
+                "Metadata": {}
+            }
+        "#;
+
+        let event: WebhookEvent = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            event,
+            WebhookEvent::Delivery(DeliveryEvent {
+                message_id: "0a129aee-e1cd-480d-b08d-4f48548ff48d".to_owned(),
+                server_id: 23,
+                recipient: "receiver@example.com".to_owned(),
+                delivered_at: Utc.ymd(2019, 11, 14).and_hms_nano(8, 45, 16, 788810300),
+                details: "Test delivery webhook".to_owned(),
+                tag: "welcome-email".to_owned(),
+                metadata: HashMap::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn dispatch_subscription_change_event() {
+        let json = r#"
+            {
+                "RecordType": "SubscriptionChange",
+                "MessageID": "0a129aee-e1cd-480d-b08d-4f48548ff48d",
+                "ServerID": 23,
+                "ChangedAt": "2019-11-14T08:45:16.7888103Z",
+                "Origin": "Recipient",
+                "Recipient": "receiver@example.com",
+                "SuppressSending": true,
+                "SuppressionReason": "HardBounce",
+                "Tag": "",
+                "Metadata": {}
+            }
+        "#;
+
+        let event: WebhookEvent = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            event,
+            WebhookEvent::SubscriptionChange(SubscriptionChangeEvent {
+                message_id: "0a129aee-e1cd-480d-b08d-4f48548ff48d".to_owned(),
+                server_id: 23,
+                changed_at: Utc.ymd(2019, 11, 14).and_hms_nano(8, 45, 16, 788810300),
+                origin: "Recipient".to_owned(),
+                recipient: "receiver@example.com".to_owned(),
+                suppress_sending: true,
+                suppression_reason: "HardBounce".to_owned(),
+                tag: "".to_owned(),
+                metadata: HashMap::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn dispatch_bounce_event() {
+        let json = r#"
+            {
+                "RecordType": "Bounce",
+                "ID": 4323372036854775807,
+                "Type": "HardBounce",
+                "TypeCode": 1,
+                "Name": "Hard bounce",
+                "Tag": "Test",
+                "MessageID": "0a129aee-e1cd-480d-b08d-4f48548ff48d",
+                "ServerID": 23,
+                "Description": "The server was unable to deliver your message",
+                "Details": "Test bounce details",
+                "Email": "john@example.com",
+                "From": "sender@example.com",
+                "BouncedAt": "2019-11-14T08:45:16.7888103Z",
+                "Inactive": true,
+                "DumpAvailable": true,
+                "CanActivate": true,
+                "Subject": "Test subject",
+                "Content": "<html>...</html>"
+            }
+        "#;
+
+        let event: WebhookEvent = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            event,
+            WebhookEvent::Bounce(BounceEvent {
+                id: 4323372036854775807,
+                r#type: "HardBounce".to_owned(),
+                type_code: 1,
+                name: "Hard bounce".to_owned(),
+                tag: "Test".to_owned(),
+                message_id: "0a129aee-e1cd-480d-b08d-4f48548ff48d".to_owned(),
+                server_id: 23,
+                description: "The server was unable to deliver your message".to_owned(),
+                details: "Test bounce details".to_owned(),
+                email: "john@example.com".to_owned(),
+                from: "sender@example.com".to_owned(),
+                bounced_at: Utc.ymd(2019, 11, 14).and_hms_nano(8, 45, 16, 788810300),
+                inactive: true,
+                dump_available: true,
+                can_activate: true,
+                subject: "Test subject".to_owned(),
+                content: "<html>...</html>".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn dispatch_open_event() {
+        let json = r#"
+            {
+                "RecordType": "Open",
+                "FirstOpen": true,
+                "Client": {
+                    "Name": "Chrome",
+                    "Company": "Google",
+                    "Family": "Chrome"
+                },
+                "OS": {
+                    "Name": "OS X",
+                    "Company": "Apple",
+                    "Family": "OS X"
+                },
+                "Platform": "WebMail",
+                "UserAgent": "Mozilla/5.0",
+                "ReadSeconds": 5,
+                "Geo": {
+                    "CountryISOCode": "US",
+                    "Country": "United States",
+                    "RegionISOCode": "NY",
+                    "Region": "New York",
+                    "City": "New York",
+                    "Zip": "10001",
+                    "Coords": "40.7143,-74.006",
+                    "IP": "188.65.36.12"
+                },
+                "MessageID": "0a129aee-e1cd-480d-b08d-4f48548ff48d",
+                "ReceivedAt": "2019-11-14T08:45:16.7888103Z",
+                "Tag": "welcome-email",
+                "Recipient": "john@example.com"
+            }
+        "#;
+
+        let event: WebhookEvent = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            event,
+            WebhookEvent::Open(OpenEvent {
+                first_open: true,
+                client: Client {
+                    name: "Chrome".to_owned(),
+                    company: "Google".to_owned(),
+                    family: "Chrome".to_owned(),
+                },
+                os: OperatingSystem {
+                    name: "OS X".to_owned(),
+                    company: "Apple".to_owned(),
+                    family: "OS X".to_owned(),
+                },
+                platform: "WebMail".to_owned(),
+                user_agent: "Mozilla/5.0".to_owned(),
+                read_seconds: 5,
+                geo: Geo {
+                    country_iso_code: "US".to_owned(),
+                    country: "United States".to_owned(),
+                    region_iso_code: "NY".to_owned(),
+                    region: "New York".to_owned(),
+                    city: "New York".to_owned(),
+                    zip: "10001".to_owned(),
+                    coords: "40.7143,-74.006".to_owned(),
+                    ip: "188.65.36.12".to_owned(),
+                },
+                message_id: "0a129aee-e1cd-480d-b08d-4f48548ff48d".to_owned(),
+                received_at: Utc.ymd(2019, 11, 14).and_hms_nano(8, 45, 16, 788810300),
+                tag: "welcome-email".to_owned(),
+                recipient: "john@example.com".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn dispatch_click_event() {
+        let json = r#"
+            {
+                "RecordType": "Click",
+                "OriginalLink": "https://example.com",
+                "ClickLocation": "HTML",
+                "Client": {
+                    "Name": "Chrome",
+                    "Company": "Google",
+                    "Family": "Chrome"
+                },
+                "OS": {
+                    "Name": "OS X",
+                    "Company": "Apple",
+                    "Family": "OS X"
+                },
+                "Platform": "WebMail",
+                "UserAgent": "Mozilla/5.0",
+                "Geo": {
+                    "CountryISOCode": "US",
+                    "Country": "United States",
+                    "RegionISOCode": "NY",
+                    "Region": "New York",
+                    "City": "New York",
+                    "Zip": "10001",
+                    "Coords": "40.7143,-74.006",
+                    "IP": "188.65.36.12"
+                },
+                "MessageID": "0a129aee-e1cd-480d-b08d-4f48548ff48d",
+                "ReceivedAt": "2019-11-14T08:45:16.7888103Z",
+                "Tag": "welcome-email",
+                "Recipient": "john@example.com"
+            }
+        "#;
+
+        let event: WebhookEvent = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            event,
+            WebhookEvent::Click(ClickEvent {
+                original_link: "https://example.com".to_owned(),
+                click_location: "HTML".to_owned(),
+                client: Client {
+                    name: "Chrome".to_owned(),
+                    company: "Google".to_owned(),
+                    family: "Chrome".to_owned(),
+                },
+                os: OperatingSystem {
+                    name: "OS X".to_owned(),
+                    company: "Apple".to_owned(),
+                    family: "OS X".to_owned(),
+                },
+                platform: "WebMail".to_owned(),
+                user_agent: "Mozilla/5.0".to_owned(),
+                geo: Geo {
+                    country_iso_code: "US".to_owned(),
+                    country: "United States".to_owned(),
+                    region_iso_code: "NY".to_owned(),
+                    region: "New York".to_owned(),
+                    city: "New York".to_owned(),
+                    zip: "10001".to_owned(),
+                    coords: "40.7143,-74.006".to_owned(),
+                    ip: "188.65.36.12".to_owned(),
+                },
+                message_id: "0a129aee-e1cd-480d-b08d-4f48548ff48d".to_owned(),
+                received_at: Utc.ymd(2019, 11, 14).and_hms_nano(8, 45, 16, 788810300),
+                tag: "welcome-email".to_owned(),
+                recipient: "john@example.com".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn dispatch_spam_complaint_event() {
+        let json = r#"
+            {
+                "RecordType": "SpamComplaint",
+                "ID": 4323372036854775807,
+                "Type": "SpamComplaint",
+                "TypeCode": 100,
+                "Name": "Spam complaint",
+                "Tag": "Test",
+                "MessageID": "0a129aee-e1cd-480d-b08d-4f48548ff48d",
+                "ServerID": 23,
+                "Description": "The recipient marked this message as spam",
+                "Details": "Test spam complaint details",
+                "Email": "john@example.com",
+                "From": "sender@example.com",
+                "BouncedAt": "2019-11-14T08:45:16.7888103Z",
+                "Inactive": true,
+                "DumpAvailable": true,
+                "CanActivate": true,
+                "Subject": "Test subject",
+                "Content": "<html>...</html>"
+            }
+        "#;
+
+        let event: WebhookEvent = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            event,
+            WebhookEvent::SpamComplaint(SpamComplaintEvent {
+                id: 4323372036854775807,
+                r#type: "SpamComplaint".to_owned(),
+                type_code: 100,
+                name: "Spam complaint".to_owned(),
+                tag: "Test".to_owned(),
+                message_id: "0a129aee-e1cd-480d-b08d-4f48548ff48d".to_owned(),
+                server_id: 23,
+                description: "The recipient marked this message as spam".to_owned(),
+                details: "Test spam complaint details".to_owned(),
+                email: "john@example.com".to_owned(),
+                from: "sender@example.com".to_owned(),
+                bounced_at: Utc.ymd(2019, 11, 14).and_hms_nano(8, 45, 16, 788810300),
+                inactive: true,
+                dump_available: true,
+                can_activate: true,
+                subject: "Test subject".to_owned(),
+                content: "<html>...</html>".to_owned(),
+            })
+        );
+    }
+}